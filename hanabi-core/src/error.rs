@@ -9,6 +9,14 @@ pub enum CoreError {
     InvalidPlayer(usize),
     #[msg(short = "incorrect player id", detailed = "{:?}", _0)]
     IncorrectInfo(CardInfo),
+    #[msg(short = "no hint tokens left")]
+    NoHintTokens,
+    #[msg(short = "cannot discard while hint tokens are full")]
+    HintTokensFull,
+    #[msg(short = "the game is already over")]
+    GameOver,
+    #[msg(short = "wrong number of strategies", detailed = "expected {} players, got {}", _0, _1)]
+    WrongStrategyCount(usize, usize),
 }
 
 pub type Error = ChainedError<CoreError>;
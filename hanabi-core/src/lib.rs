@@ -12,14 +12,32 @@ extern crate serde;
 extern crate serde_derive;
 extern crate uuid;
 
+#[cfg(feature = "server")]
+extern crate async_std;
+#[cfg(feature = "server")]
+extern crate async_tungstenite;
+#[cfg(feature = "server")]
+extern crate futures;
+#[cfg(feature = "server")]
+extern crate serde_json;
+
 mod error;
+mod simulate;
+#[cfg(feature = "server")]
+mod server;
+mod strategy;
+
+pub use simulate::{play_game, simulate, SimulationReport};
+#[cfg(feature = "server")]
+pub use server::{serve, ClientMessage, ServerMessage};
+pub use strategy::{InformationStrategy, Observation, PlayerView, RandomStrategy, Strategy};
 
 use error::{CoreError, Error, ErrorKind, ResultExt};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 /// colors of hanabi cards
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, EnumIterator)]
 pub enum Color {
@@ -66,6 +84,42 @@ impl Number {
     }
 }
 
+/// how many copies of a card with the given value a standard deck contains
+pub fn get_count_for_value(n: Number) -> usize {
+    match n {
+        Number::One => 3,
+        Number::Two | Number::Three | Number::Four => 2,
+        Number::Five => 1,
+    }
+}
+
+/// tracks how many copies of each (Color, Number) card have been discarded so far,
+/// so callers can tell whether a suit is still completable
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CardCounts {
+    inner: HashMap<(Color, Number), usize>,
+}
+
+impl CardCounts {
+    fn new() -> Self {
+        CardCounts {
+            inner: HashMap::new(),
+        }
+    }
+    /// record that a copy of `card` has been discarded
+    fn record_discard(&mut self, card: &Card) {
+        *self.inner.entry((card.color, card.number)).or_insert(0) += 1;
+    }
+    /// how many copies of this color/number have been discarded so far
+    pub fn discarded(&self, color: Color, number: Number) -> usize {
+        self.inner.get(&(color, number)).cloned().unwrap_or(0)
+    }
+    /// whether at least one copy of this color/number has not yet been discarded
+    pub fn is_completable(&self, color: Color, number: Number) -> bool {
+        self.discarded(color, number) < get_count_for_value(number)
+    }
+}
+
 /// unique identifier of cards
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct CardId(Uuid);
@@ -87,6 +141,16 @@ impl Card {
             id: CardId(uuid),
         }
     }
+    /// like `new`, but derives `id` from `rng` instead of OS randomness, so
+    /// two decks built from the same seed get matching `CardId`s and
+    /// `Action::Play`/`Action::Discard` from a `GameLog` replay correctly
+    fn new_seeded<R: Rng>(n: Number, c: Color, rng: &mut R) -> Self {
+        Card {
+            number: n,
+            color: c,
+            id: CardId(Uuid::from_u128(rng.gen())),
+        }
+    }
 }
 
 /// token
@@ -144,6 +208,12 @@ pub struct CardInfo {
     player: usize,
 }
 
+impl CardInfo {
+    pub fn new(player: usize, kind: CardInfoKind) -> Self {
+        CardInfo { kind, player }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct CardInfoInner {
     kind: CardInfoKind,
@@ -151,6 +221,13 @@ struct CardInfoInner {
     player: usize,
 }
 
+/// what applying a validated `Action` will do, computed ahead of time by `check_action`
+enum ActionEffects {
+    Discard { idx: usize },
+    Play { idx: usize, lands: bool },
+    Tell(CardInfoInner),
+}
+
 /// kind of card information
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum CardInfoKind {
@@ -158,6 +235,27 @@ pub enum CardInfoKind {
     Number(Number),
 }
 
+/// what a player still considers possible for one of their own cards
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CardKnowledge {
+    pub colors: HashSet<Color>,
+    pub numbers: HashSet<Number>,
+}
+
+impl CardKnowledge {
+    /// knowledge for a freshly drawn, never-hinted card
+    fn unknown(is_multi: bool) -> Self {
+        let suits = if is_multi { 5 } else { 6 };
+        CardKnowledge {
+            colors: Color::iter_variants().take(suits).collect(),
+            numbers: Number::iter_variants().collect(),
+        }
+    }
+}
+
+/// per-player accumulated knowledge about their own hand, keyed by `CardId`
+pub type Knowledge = HashMap<usize, HashMap<CardId, CardKnowledge>>;
+
 /// cards in field
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Field {
@@ -171,18 +269,65 @@ impl Field {
         }
     }
     pub fn add(&mut self, card: Card) -> bool {
-        let id = card.color.to_usize();
-        let last_number = match self.inner[id].last() {
+        if !self.would_land(card.color, card.number) {
+            return false;
+        }
+        self.inner[card.color.to_usize()].push(card);
+        true
+    }
+    /// whether a card of this color/number would currently be accepted by `add`
+    pub fn would_land(&self, color: Color, number: Number) -> bool {
+        let last_number = match self.inner[color.to_usize()].last() {
             Some(card) => card.number.to_usize(),
             None => 0,
         };
-        if last_number + 1 == card.number.to_usize() {
-            self.inner[id].push(card);
-            true
-        } else {
-            false
-        }
+        last_number + 1 == number.to_usize()
+    }
+    /// whether the first `suits` stacks are all complete, i.e. topped with a Five
+    fn is_complete(&self, suits: usize) -> bool {
+        self.inner[..suits]
+            .iter()
+            .all(|stack| stack.last().map(|card| card.number.to_usize()) == Some(5))
+    }
+}
+
+/// maximum number of hint(blue) tokens a game can hold at once
+const MAX_HINT_TOKENS: usize = 8;
+/// number of fuse(red) tokens a game starts with
+const MAX_FUSE_TOKENS: usize = 3;
+
+/// overall state of a game
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// seed plus the ordered `(player, Action)` history applied to a game,
+/// enough to reconstruct its exact final state with `replay`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameLog {
+    pub seed: u64,
+    pub player_num: usize,
+    pub is_multi: bool,
+    pub is_grand_finale: bool,
+    pub actions: Vec<(usize, Action)>,
+}
+
+/// replay a recorded game from its log, reconstructing the exact final state
+pub fn replay(log: &GameLog) -> Result<Game, Error> {
+    let mut config =
+        Config::new(log.player_num).ok_or_else(|| CoreError::InvalidPlayer(log.player_num).into_err())?;
+    config
+        .multi(log.is_multi)
+        .grand_finale(log.is_grand_finale)
+        .seed(log.seed);
+    let mut game = config.build();
+    for &(player, ref act) in &log.actions {
+        game.process_action(player, act.to_owned())?;
     }
+    Ok(game)
 }
 
 /// game information(runtime)
@@ -196,61 +341,255 @@ pub struct Game {
     pub discards: Vec<Card>,
     /// hanabi field
     pub field: Field,
+    /// how many copies of each card have been discarded
+    pub discard_counts: CardCounts,
+    /// remaining hint(blue) tokens
+    pub hint_tokens: usize,
+    /// remaining fuse(red) tokens
+    pub fuse_tokens: usize,
+    /// current status of the game
+    pub status: GameStatus,
+    /// turns left after the stack has been drawn empty, if any
+    final_turns_left: Option<usize>,
+    /// what each player has been told about their own hand so far
+    pub knowledge: Knowledge,
+    /// seed and ordered action history needed to replay this game exactly
+    pub log: GameLog,
     player_num: usize,
     is_multi: bool,
     is_grand_finale: bool,
 }
 
 impl Game {
-    fn process_action(
-        &mut self,
-        player: usize,
-        act: Action,
-    ) -> Result<Option<CardInfoInner>, Error> {
+    /// compute what applying `act` for `player` would do, without mutating `self`
+    fn check_action(&self, player: usize, act: &Action) -> Result<ActionEffects, Error> {
         if !self.is_valid_player(player) {
             return Err(CoreError::InvalidPlayer(player).into_err());
         }
-        macro_rules! get_card {
-            ($id:ident) => {
-                if let Some(idx) = self.players[player].card_idx($id) {
-                    self.players[player].remove_card(idx)
-                } else {
-                    return Err(CoreError::InvalidCard($id).into_err());
-                }
-            };
+        if self.status != GameStatus::Playing {
+            return Err(CoreError::GameOver.into_err());
         }
-        let res = match act {
+        match *act {
             Action::Discard(id) => {
-                let card = get_card!(id);
-                self.discards.push(card);
-                None
+                if self.hint_tokens >= MAX_HINT_TOKENS {
+                    return Err(CoreError::HintTokensFull.into_err());
+                }
+                let idx = self.players[player]
+                    .card_idx(id)
+                    .ok_or_else(|| CoreError::InvalidCard(id).into_err())?;
+                Ok(ActionEffects::Discard { idx })
             }
             Action::Play(id) => {
-                let card = get_card!(id);
-                if !self.field.add(card) {
-                    return Err(CoreError::InvalidCard(id).into_err());
-                }
-                None
+                let idx = self.players[player]
+                    .card_idx(id)
+                    .ok_or_else(|| CoreError::InvalidCard(id).into_err())?;
+                let card = self.players[player].hands[idx];
+                let lands = self.field.would_land(card.color, card.number);
+                Ok(ActionEffects::Play { idx, lands })
             }
-            Action::Tell(ref info) => if let Some(cards) = self.construct_info(info) {
+            Action::Tell(ref info) => {
+                if self.hint_tokens == 0 {
+                    return Err(CoreError::NoHintTokens.into_err());
+                }
+                let cards = self
+                    .construct_info(info)
+                    .ok_or_else(|| CoreError::IncorrectInfo(info.to_owned()).into_err())?;
                 if cards.is_empty() {
                     return Err(CoreError::IncorrectInfo(info.to_owned()).into_err());
                 }
-                let info_inner = CardInfoInner {
+                Ok(ActionEffects::Tell(CardInfoInner {
                     kind: info.kind,
                     player: info.player,
                     cards,
-                };
-                Some(info_inner)
-            } else {
-                return Err(CoreError::IncorrectInfo(info.to_owned()).into_err());
-            },
-        };
-        Ok(res)
+                }))
+            }
+        }
+    }
+    pub fn process_action(&mut self, player: usize, act: Action) -> Result<(), Error> {
+        let effects = self.check_action(player, &act)?;
+        self.log.actions.push((player, act));
+        // snapshot before any draw this action triggers: the final round starts
+        // the turn *after* the stack is drawn empty, so every action taken while
+        // it was already empty ticks the countdown, not just card-consuming ones
+        let deck_was_empty = self.stack.is_empty();
+        match effects {
+            ActionEffects::Discard { idx } => {
+                let card = self.players[player].remove_card(idx);
+                self.knowledge.get_mut(&player).unwrap().remove(&card.id);
+                self.discard_counts.record_discard(&card);
+                self.discards.push(card);
+                self.hint_tokens += 1;
+                self.draw_for(player);
+            }
+            ActionEffects::Play { idx, lands } => {
+                let card = self.players[player].remove_card(idx);
+                self.knowledge.get_mut(&player).unwrap().remove(&card.id);
+                if lands {
+                    self.field.add(card);
+                } else {
+                    self.discard_counts.record_discard(&card);
+                    self.discards.push(card);
+                    self.fuse_tokens -= 1;
+                }
+                self.draw_for(player);
+            }
+            ActionEffects::Tell(info) => {
+                self.hint_tokens -= 1;
+                self.update_knowledge_for_tell(&info);
+            }
+        }
+        if deck_was_empty {
+            self.advance_final_round();
+        }
+        self.update_status();
+        Ok(())
+    }
+    /// enumerate every currently-valid play, discard, and tell for `player`
+    pub fn legal_actions(&self, player: usize) -> Vec<Action> {
+        let mut actions = Vec::new();
+        if !self.is_valid_player(player) || self.status != GameStatus::Playing {
+            return actions;
+        }
+        for card in &self.players[player].hands {
+            actions.push(Action::Play(card.id));
+            if self.hint_tokens < MAX_HINT_TOKENS {
+                actions.push(Action::Discard(card.id));
+            }
+        }
+        if self.hint_tokens > 0 {
+            for other in 0..self.player_num {
+                if other == player {
+                    continue;
+                }
+                let mut seen_colors = HashSet::new();
+                let mut seen_numbers = HashSet::new();
+                for card in &self.players[other].hands {
+                    if seen_colors.insert(card.color) {
+                        actions.push(Action::Tell(CardInfo::new(
+                            other,
+                            CardInfoKind::Color(card.color),
+                        )));
+                    }
+                    if seen_numbers.insert(card.number) {
+                        actions.push(Action::Tell(CardInfo::new(
+                            other,
+                            CardInfoKind::Number(card.number),
+                        )));
+                    }
+                }
+            }
+        }
+        actions
+    }
+    /// narrow (or eliminate) the told color/number for every card in the
+    /// hinted player's hand, per the rules of a Hanabi `Tell`
+    fn update_knowledge_for_tell(&mut self, info: &CardInfoInner) {
+        let hand = &self.players[info.player].hands;
+        let per_card = self.knowledge.get_mut(&info.player).unwrap();
+        for card in hand {
+            let known = match per_card.get_mut(&card.id) {
+                Some(known) => known,
+                None => continue,
+            };
+            let hinted = info.cards.contains(&card.id);
+            match info.kind {
+                CardInfoKind::Color(c) => if hinted {
+                    known.colors = [c].iter().cloned().collect();
+                } else {
+                    known.colors.remove(&c);
+                },
+                CardInfoKind::Number(n) => if hinted {
+                    known.numbers = [n].iter().cloned().collect();
+                } else {
+                    known.numbers.remove(&n);
+                },
+            }
+        }
+    }
+    /// what a player still considers possible for one of their own cards
+    pub fn possible_cards(&self, player: usize, id: CardId) -> Option<&CardKnowledge> {
+        self.knowledge.get(&player)?.get(&id)
+    }
+    /// build `player`'s view of the game for a `Strategy`: their own hand is
+    /// hidden behind `CardId`s, everything else is fully visible.
+    /// `None` if `player` is not a valid player of this game
+    pub fn observation(&self, player: usize) -> Option<Observation> {
+        if !self.is_valid_player(player) {
+            return None;
+        }
+        let own_hand = self.players[player].hands.iter().map(|c| c.id).collect();
+        let others = self
+            .players
+            .iter()
+            .filter(|p| p.player_id != player)
+            .cloned()
+            .collect();
+        Some(Observation {
+            player,
+            own_hand,
+            others,
+            field: self.field.clone(),
+            discards: self.discards.clone(),
+            hint_tokens: self.hint_tokens,
+            fuse_tokens: self.fuse_tokens,
+            knowledge: self.knowledge.clone(),
+        })
+    }
+    /// like `observation`, but named for the consumers (e.g. the websocket
+    /// server) that send it to a remote client rather than a local `Strategy`
+    pub fn view_for(&self, player: usize) -> Option<PlayerView> {
+        self.observation(player)
     }
     fn is_valid_player(&self, n: usize) -> bool {
         n < self.player_num
     }
+    /// draw a replacement card for `player` from the stack, if any remain
+    fn draw_for(&mut self, player: usize) {
+        if let Some(card) = self.stack.pop() {
+            self.knowledge
+                .get_mut(&player)
+                .unwrap()
+                .insert(card.id, CardKnowledge::unknown(self.is_multi));
+            self.players[player].hands.push(card);
+        }
+    }
+    /// tick the countdown to the end of the game, started once the stack
+    /// has been drawn empty; every action taken from then on counts,
+    /// regardless of whether it drew a card
+    fn advance_final_round(&mut self) {
+        self.final_turns_left = Some(match self.final_turns_left {
+            Some(turns) => turns.saturating_sub(1),
+            None => if self.is_grand_finale {
+                self.player_num
+            } else {
+                self.player_num - 1
+            },
+        });
+    }
+    fn active_suits(&self) -> usize {
+        if self.is_multi {
+            5
+        } else {
+            6
+        }
+    }
+    /// sum of the top card of every suit stack, complete or not
+    pub fn score(&self) -> usize {
+        self.field
+            .inner
+            .iter()
+            .map(|stack| stack.last().map(|card| card.number.to_usize()).unwrap_or(0))
+            .sum()
+    }
+    fn update_status(&mut self) {
+        if self.fuse_tokens == 0 {
+            self.status = GameStatus::Lost;
+        } else if self.field.is_complete(self.active_suits()) {
+            self.status = GameStatus::Won;
+        } else if self.final_turns_left == Some(0) {
+            self.status = GameStatus::Lost;
+        }
+    }
     fn construct_info(&self, info: &CardInfo) -> Option<HashSet<CardId>> {
         if !self.is_valid_player(info.player) {
             return None;
@@ -276,6 +615,7 @@ pub struct Config {
     player_num: usize,
     is_multi: bool,
     is_grand_finale: bool,
+    seed: u64,
 }
 
 impl Config {
@@ -287,6 +627,7 @@ impl Config {
             player_num: n,
             is_multi: false,
             is_grand_finale: false,
+            seed: rand::random(),
         })
     }
     pub fn multi(&mut self, f: bool) -> &mut Self {
@@ -297,10 +638,29 @@ impl Config {
         self.is_grand_finale = f;
         self
     }
+    /// fix the RNG seed used to shuffle and deal the deck, for reproducible games
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = seed;
+        self
+    }
+    pub fn player_num(&self) -> usize {
+        self.player_num
+    }
     pub fn build(self) -> Game {
         let n = self.player_num;
         let is_multi = self.is_multi;
-        let (player_cards, stack) = prepare_cards(n, is_multi);
+        let (player_cards, stack) = prepare_cards(n, is_multi, self.seed);
+        let knowledge = player_cards
+            .iter()
+            .enumerate()
+            .map(|(i, hands)| {
+                let per_card = hands
+                    .iter()
+                    .map(|card| (card.id, CardKnowledge::unknown(is_multi)))
+                    .collect();
+                (i, per_card)
+            })
+            .collect();
         let players: Vec<_> = player_cards
             .into_iter()
             .enumerate()
@@ -311,6 +671,19 @@ impl Config {
             stack,
             discards: Vec::new(),
             field: Field::new(),
+            discard_counts: CardCounts::new(),
+            hint_tokens: MAX_HINT_TOKENS,
+            fuse_tokens: MAX_FUSE_TOKENS,
+            status: GameStatus::Playing,
+            final_turns_left: None,
+            knowledge,
+            log: GameLog {
+                seed: self.seed,
+                player_num: n,
+                is_multi,
+                is_grand_finale: self.is_grand_finale,
+                actions: Vec::new(),
+            },
             player_num: n,
             is_multi,
             is_grand_finale: self.is_grand_finale,
@@ -318,19 +691,17 @@ impl Config {
     }
 }
 
-fn prepare_cards(n: usize, is_multi: bool) -> (Vec<Vec<Card>>, Vec<Card>) {
-    let mut stack_cards: Vec<_> = {
-        let card_kinds = if is_multi { 5 } else { 6 };
-        Color::iter_variants()
-            .take(card_kinds)
-            .flat_map(|var| {
-                Number::iter_variants()
-                    .map(|num| Card::new(num, var))
-                    .collect::<Vec<_>>()
-            })
-            .collect()
-    };
-    let mut rng = rand::thread_rng();
+fn prepare_cards(n: usize, is_multi: bool, seed: u64) -> (Vec<Vec<Card>>, Vec<Card>) {
+    let mut rng = rand::isaac::Isaac64Rng::new_from_u64(seed);
+    let card_kinds = if is_multi { 5 } else { 6 };
+    let mut stack_cards = Vec::new();
+    for var in Color::iter_variants().take(card_kinds) {
+        for num in Number::iter_variants() {
+            for _ in 0..get_count_for_value(num) {
+                stack_cards.push(Card::new_seeded(num, var, &mut rng));
+            }
+        }
+    }
     rng.shuffle(&mut stack_cards);
     let card_num = if n <= 3 { 5 } else { 4 };
     let mut hands = vec![vec![]; n];
@@ -342,3 +713,237 @@ fn prepare_cards(n: usize, is_multi: bool) -> (Vec<Vec<Card>>, Vec<Card>) {
     }
     (hands, stack_cards)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_count_for_value_matches_standard_deck() {
+        assert_eq!(get_count_for_value(Number::One), 3);
+        assert_eq!(get_count_for_value(Number::Two), 2);
+        assert_eq!(get_count_for_value(Number::Three), 2);
+        assert_eq!(get_count_for_value(Number::Four), 2);
+        assert_eq!(get_count_for_value(Number::Five), 1);
+    }
+
+    #[test]
+    fn card_counts_is_completable_until_every_copy_is_discarded() {
+        let mut counts = CardCounts::new();
+        assert!(counts.is_completable(Color::Red, Number::Two));
+        counts.record_discard(&Card::new(Number::Two, Color::Red));
+        assert!(counts.is_completable(Color::Red, Number::Two));
+        counts.record_discard(&Card::new(Number::Two, Color::Red));
+        assert!(!counts.is_completable(Color::Red, Number::Two));
+    }
+
+    #[test]
+    fn deck_has_one_copy_of_every_five_per_active_suit() {
+        let config = Config::new(4).unwrap();
+        let (hands, stack) = prepare_cards(config.player_num, config.is_multi, config.seed);
+        let all_cards: Vec<_> = hands.into_iter().flatten().chain(stack).collect();
+        for color in Color::iter_variants().take(6) {
+            let fives = all_cards
+                .iter()
+                .filter(|card| card.color == color && card.number == Number::Five)
+                .count();
+            assert_eq!(fives, 1);
+        }
+    }
+
+    #[test]
+    fn score_sums_top_card_of_each_stack() {
+        let mut game = Config::new(2).unwrap().build();
+        game.field.inner[Color::Red.to_usize()] =
+            vec![Card::new(Number::One, Color::Red), Card::new(Number::Two, Color::Red)];
+        game.field.inner[Color::Blue.to_usize()] = vec![Card::new(Number::One, Color::Blue)];
+        assert_eq!(game.score(), 3);
+    }
+
+    #[test]
+    fn losing_all_fuse_tokens_ends_the_game() {
+        let mut game = Config::new(2).unwrap().build();
+        game.fuse_tokens = 0;
+        game.update_status();
+        assert_eq!(game.status, GameStatus::Lost);
+    }
+
+    #[test]
+    fn completing_every_suit_wins_the_game() {
+        let mut game = Config::new(2).unwrap().build();
+        for color in Color::iter_variants().take(6) {
+            game.field.inner[color.to_usize()] = vec![Card::new(Number::Five, color)];
+        }
+        game.update_status();
+        assert_eq!(game.status, GameStatus::Won);
+    }
+
+    #[test]
+    fn tell_actions_advance_the_final_round_once_the_deck_is_empty() {
+        let mut config = Config::new(2).unwrap();
+        config.seed(42);
+        let mut game = config.build();
+        game.stack.clear();
+
+        let other_color = game.players[1].hands[0].color;
+        game.process_action(0, Action::Tell(CardInfo::new(1, CardInfoKind::Color(other_color))))
+            .unwrap();
+        assert_eq!(game.status, GameStatus::Playing);
+
+        let color = game.players[0].hands[0].color;
+        game.process_action(1, Action::Tell(CardInfo::new(0, CardInfoKind::Color(color))))
+            .unwrap();
+        assert_eq!(game.status, GameStatus::Lost);
+    }
+
+    #[test]
+    fn telling_a_color_narrows_knowledge_for_that_players_hand() {
+        let mut game = Config::new(2).unwrap().build();
+        let told_color = game.players[1].hands[0].color;
+        game.process_action(0, Action::Tell(CardInfo::new(1, CardInfoKind::Color(told_color))))
+            .unwrap();
+        for card in game.players[1].hands.clone() {
+            let known = game.possible_cards(1, card.id).unwrap();
+            if card.color == told_color {
+                assert_eq!(known.colors, [told_color].iter().cloned().collect());
+            } else {
+                assert!(!known.colors.contains(&told_color));
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_hands() {
+        let (hands_a, stack_a) = prepare_cards(3, false, 7);
+        let (hands_b, stack_b) = prepare_cards(3, false, 7);
+        let seq_a: Vec<_> = hands_a
+            .iter()
+            .flatten()
+            .chain(&stack_a)
+            .map(|c| (c.color, c.number))
+            .collect();
+        let seq_b: Vec<_> = hands_b
+            .iter()
+            .flatten()
+            .chain(&stack_b)
+            .map(|c| (c.color, c.number))
+            .collect();
+        assert_eq!(seq_a, seq_b);
+    }
+
+    #[test]
+    fn same_seed_deals_matching_card_ids() {
+        let (hands_a, stack_a) = prepare_cards(3, false, 11);
+        let (hands_b, stack_b) = prepare_cards(3, false, 11);
+        let ids_a: Vec<_> = hands_a.iter().flatten().chain(&stack_a).map(|c| c.id).collect();
+        let ids_b: Vec<_> = hands_b.iter().flatten().chain(&stack_b).map(|c| c.id).collect();
+        assert_eq!(ids_a, ids_b);
+    }
+
+    #[test]
+    fn replay_reconstructs_the_exact_final_state() {
+        let mut config = Config::new(2).unwrap();
+        config.seed(99);
+        let mut game = config.build();
+
+        let color1 = game.players[1].hands[0].color;
+        game.process_action(0, Action::Tell(CardInfo::new(1, CardInfoKind::Color(color1))))
+            .unwrap();
+
+        let discard_id = game.players[1].hands[1].id;
+        game.process_action(1, Action::Discard(discard_id)).unwrap();
+
+        let play_id = game.players[0].hands[0].id;
+        game.process_action(0, Action::Play(play_id)).unwrap();
+
+        let replayed = replay(&game.log).unwrap();
+        assert_eq!(replayed.hint_tokens, game.hint_tokens);
+        assert_eq!(replayed.fuse_tokens, game.fuse_tokens);
+        assert_eq!(replayed.status, game.status);
+        assert_eq!(replayed.score(), game.score());
+        let orig_hands: Vec<_> = game
+            .players
+            .iter()
+            .map(|p| p.hands.iter().map(|c| c.id).collect::<Vec<_>>())
+            .collect();
+        let replay_hands: Vec<_> = replayed
+            .players
+            .iter()
+            .map(|p| p.hands.iter().map(|c| c.id).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(orig_hands, replay_hands);
+    }
+
+    #[test]
+    fn replay_surfaces_an_invalid_player_count_instead_of_panicking() {
+        let log = GameLog {
+            seed: 0,
+            player_num: 1,
+            is_multi: false,
+            is_grand_finale: false,
+            actions: Vec::new(),
+        };
+        assert!(replay(&log).is_err());
+    }
+
+    fn is_discard(action: &Action) -> bool {
+        match *action {
+            Action::Discard(_) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn legal_actions_excludes_discards_while_hint_tokens_are_full() {
+        let game = Config::new(2).unwrap().build();
+        assert_eq!(game.hint_tokens, MAX_HINT_TOKENS);
+        let actions = game.legal_actions(0);
+        assert!(actions.iter().all(|a| !is_discard(a)));
+    }
+
+    #[test]
+    fn legal_actions_includes_a_discard_per_card_once_a_hint_token_is_spent() {
+        let mut game = Config::new(2).unwrap().build();
+        let color = game.players[1].hands[0].color;
+        game.process_action(0, Action::Tell(CardInfo::new(1, CardInfoKind::Color(color))))
+            .unwrap();
+        assert!(game.hint_tokens < MAX_HINT_TOKENS);
+        let discard_count = game.legal_actions(0).iter().filter(|a| is_discard(a)).count();
+        assert_eq!(discard_count, game.players[0].hands.len());
+    }
+
+    #[test]
+    fn legal_actions_enumerates_each_distinct_tell_once_per_other_player() {
+        let game = Config::new(3).unwrap().build();
+        let actions = game.legal_actions(0);
+        let tells: Vec<_> = actions
+            .iter()
+            .filter_map(|a| match *a {
+                Action::Tell(ref info) => Some(info),
+                _ => None,
+            })
+            .collect();
+        assert!(!tells.is_empty());
+        for other in 0..3 {
+            if other == 0 {
+                continue;
+            }
+            assert!(tells.iter().any(|info| info.player == other));
+        }
+        for i in 0..tells.len() {
+            for j in (i + 1)..tells.len() {
+                let dup = tells[i].player == tells[j].player && tells[i].kind == tells[j].kind;
+                assert!(!dup, "duplicate tell action");
+            }
+        }
+    }
+
+    #[test]
+    fn legal_actions_is_empty_for_an_invalid_player_or_a_finished_game() {
+        let mut game = Config::new(2).unwrap().build();
+        assert!(game.legal_actions(99).is_empty());
+        game.fuse_tokens = 0;
+        game.update_status();
+        assert!(game.legal_actions(0).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! optional websocket game server, so multiple clients can play one `Game`
+//! remotely. modeled on small tide/async-std game servers: the authoritative
+//! `Game` lives behind an `Arc<RwLock<_>>` and every connected client gets
+//! its own filtered `PlayerView`, re-broadcast after each successfully
+//! applied action.
+//!
+//! gated behind the `server` feature so the core rules crate stays usable
+//! without pulling in an async runtime.
+
+use std::io;
+use std::sync::Arc;
+
+use async_std::net::{TcpListener, TcpStream};
+use async_std::sync::RwLock;
+use async_std::task;
+use async_tungstenite::accept_async;
+use async_tungstenite::tungstenite::Message;
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use {Action, CardId, CardInfo, Game, PlayerView};
+
+/// a message sent from a client to the server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ClientMessage {
+    Join { player: usize },
+    Tell(CardInfo),
+    Discard(CardId),
+    Play(CardId),
+}
+
+impl ClientMessage {
+    fn into_action(self) -> Option<Action> {
+        match self {
+            ClientMessage::Tell(info) => Some(Action::Tell(info)),
+            ClientMessage::Discard(id) => Some(Action::Discard(id)),
+            ClientMessage::Play(id) => Some(Action::Play(id)),
+            ClientMessage::Join { .. } => None,
+        }
+    }
+}
+
+/// a message sent from the server to a client
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum ServerMessage {
+    State(PlayerView),
+    Error(String),
+}
+
+type Clients = Arc<RwLock<Vec<(usize, UnboundedSender<Message>)>>>;
+
+/// serve `game` over websockets at `addr`, broadcasting a filtered
+/// `PlayerView` to every connected client after each successfully applied
+/// action
+pub async fn serve(addr: &str, game: Game) -> io::Result<()> {
+    let game = Arc::new(RwLock::new(game));
+    let clients: Clients = Arc::new(RwLock::new(Vec::new()));
+    let listener = TcpListener::bind(addr).await?;
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        task::spawn(handle_connection(stream?, game.clone(), clients.clone()));
+    }
+    Ok(())
+}
+
+async fn handle_connection(stream: TcpStream, game: Arc<RwLock<Game>>, clients: Clients) {
+    let ws = match accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(_) => return,
+    };
+    let (mut outgoing, mut incoming) = ws.split();
+    let (tx, mut rx) = unbounded();
+    task::spawn(async move {
+        while let Some(msg) = rx.next().await {
+            if outgoing.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut player = None;
+    while let Some(Ok(msg)) = incoming.next().await {
+        let client_msg: ClientMessage = match msg.to_text().ok().and_then(|t| serde_json::from_str(t).ok()) {
+            Some(m) => m,
+            None => continue,
+        };
+        if let ClientMessage::Join { player: id } = client_msg {
+            match game.read().await.view_for(id) {
+                Some(view) => {
+                    player = Some(id);
+                    clients.write().await.push((id, tx.clone()));
+                    send(&tx, &ServerMessage::State(view));
+                }
+                None => send(&tx, &ServerMessage::Error(format!("no such player: {}", id))),
+            }
+            continue;
+        }
+        let player = match player {
+            Some(id) => id,
+            None => continue,
+        };
+        let action = match client_msg.into_action() {
+            Some(act) => act,
+            None => continue,
+        };
+        let mut game = game.write().await;
+        let expected = whose_turn(&game);
+        if player != expected {
+            send(
+                &tx,
+                &ServerMessage::Error(format!("not your turn: player {} is", expected)),
+            );
+            continue;
+        }
+        match game.process_action(player, action) {
+            Ok(()) => broadcast(&game, &clients).await,
+            Err(err) => send(&tx, &ServerMessage::Error(err.to_string())),
+        }
+    }
+    if let Some(id) = player {
+        clients.write().await.retain(|&(other, _)| other != id);
+    }
+}
+
+/// whose turn it is: players act in a fixed round-robin by index, the same
+/// rotation `simulate::play_game` drives locally, derived from how many
+/// actions have been recorded so far
+fn whose_turn(game: &Game) -> usize {
+    game.log.actions.len() % game.players.len()
+}
+
+async fn broadcast(game: &Game, clients: &Clients) {
+    for (id, tx) in clients.read().await.iter() {
+        if let Some(view) = game.view_for(*id) {
+            send(tx, &ServerMessage::State(view));
+        }
+    }
+}
+
+fn send(tx: &UnboundedSender<Message>, msg: &ServerMessage) {
+    let text = serde_json::to_string(msg).expect("ServerMessage always serializes");
+    let _ = tx.unbounded_send(Message::Text(text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {CardInfoKind, Color, Config};
+
+    #[test]
+    fn into_action_maps_gameplay_messages_and_rejects_join() {
+        let game = Config::new(2).unwrap().build();
+        let card_id = game.players[0].hands[0].id;
+
+        assert!(ClientMessage::Join { player: 0 }.into_action().is_none());
+
+        match ClientMessage::Discard(card_id).into_action() {
+            Some(Action::Discard(id)) => assert_eq!(id, card_id),
+            other => panic!("expected Discard, got {:?}", other),
+        }
+        match ClientMessage::Play(card_id).into_action() {
+            Some(Action::Play(id)) => assert_eq!(id, card_id),
+            other => panic!("expected Play, got {:?}", other),
+        }
+        match ClientMessage::Tell(CardInfo::new(1, CardInfoKind::Color(Color::Red))).into_action() {
+            Some(Action::Tell(_)) => {}
+            other => panic!("expected Tell, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn join_distinguishes_valid_from_invalid_player_ids() {
+        let game = Config::new(2).unwrap().build();
+        assert!(game.view_for(0).is_some());
+        assert!(game.view_for(99).is_none());
+    }
+
+    #[test]
+    fn disconnect_prunes_only_the_disconnecting_players_entry() {
+        let (tx0, _rx0) = unbounded();
+        let (tx1, _rx1) = unbounded();
+        let mut clients: Vec<(usize, UnboundedSender<Message>)> = vec![(0, tx0), (1, tx1)];
+        let id = 0;
+        clients.retain(|&(other, _)| other != id);
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].0, 1);
+    }
+
+    #[test]
+    fn whose_turn_rotates_through_players_in_order() {
+        let mut game = Config::new(2).unwrap().build();
+        assert_eq!(whose_turn(&game), 0);
+        let color = game.players[1].hands[0].color;
+        game.process_action(0, Action::Tell(CardInfo::new(1, CardInfoKind::Color(color))))
+            .unwrap();
+        assert_eq!(whose_turn(&game), 1);
+    }
+}
@@ -0,0 +1,112 @@
+//! batch simulation harness for benchmarking `Strategy` implementations
+
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use error::{CoreError, Error, ErrorKind};
+use strategy::Strategy;
+use {Config, Game, GameStatus};
+
+/// score distribution over a batch of simulated games
+#[derive(Clone, Debug)]
+pub struct SimulationReport {
+    pub games_played: usize,
+    pub mean_score: f64,
+    pub histogram: HashMap<usize, usize>,
+    pub loss_rate: f64,
+}
+
+/// play one game to completion, driving `strategies[i]` for player `i`.
+/// `strategies` must have exactly as many entries as `config`'s player count
+pub fn play_game(config: Config, strategies: &mut [Box<Strategy>]) -> Result<Game, Error> {
+    if strategies.len() != config.player_num() {
+        return Err(CoreError::WrongStrategyCount(config.player_num(), strategies.len()).into_err());
+    }
+    let mut game = config.build();
+    let mut current = 0;
+    while game.status == GameStatus::Playing {
+        let obs = game
+            .observation(current)
+            .expect("current is always a valid player index");
+        let action = strategies[current].decide(&obs);
+        game.process_action(current, action)?;
+        current = (current + 1) % strategies.len();
+    }
+    Ok(game)
+}
+
+/// play `n` independent games from `config` and summarize the resulting scores
+pub fn simulate(
+    config: &Config,
+    strategies: &mut [Box<Strategy>],
+    n: usize,
+) -> Result<SimulationReport, Error> {
+    let mut histogram = HashMap::new();
+    let mut total_score = 0usize;
+    let mut losses = 0usize;
+    let mut rng = rand::thread_rng();
+    for _ in 0..n {
+        let mut game_config = config.clone();
+        game_config.seed(rng.gen());
+        let game = play_game(game_config, strategies)?;
+        let score = game.score();
+        total_score += score;
+        *histogram.entry(score).or_insert(0) += 1;
+        if game.status == GameStatus::Lost {
+            losses += 1;
+        }
+    }
+    Ok(SimulationReport {
+        games_played: n,
+        mean_score: total_score as f64 / n as f64,
+        histogram,
+        loss_rate: losses as f64 / n as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strategy::{Observation, RandomStrategy};
+    use Action;
+
+    /// always plays the first card in hand, regardless of whether it lands;
+    /// deterministic so a test can attribute score differences to the deal
+    struct AlwaysPlayFirst;
+
+    impl Strategy for AlwaysPlayFirst {
+        fn decide(&mut self, obs: &Observation) -> Action {
+            Action::Play(obs.own_hand[0])
+        }
+    }
+
+    #[test]
+    fn play_game_rejects_a_strategy_count_mismatch() {
+        let config = Config::new(3).unwrap();
+        let mut strategies: Vec<Box<Strategy>> =
+            vec![Box::new(RandomStrategy::new(rand::thread_rng()))];
+        assert!(play_game(config, &mut strategies).is_err());
+    }
+
+    #[test]
+    fn play_game_runs_a_matched_strategy_count_to_completion() {
+        let config = Config::new(2).unwrap();
+        let mut strategies: Vec<Box<Strategy>> =
+            vec![Box::new(AlwaysPlayFirst), Box::new(AlwaysPlayFirst)];
+        let game = play_game(config, &mut strategies).unwrap();
+        assert_ne!(game.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn simulate_reseeds_each_game_instead_of_reusing_the_same_deal() {
+        let config = Config::new(2).unwrap();
+        let mut strategies: Vec<Box<Strategy>> =
+            vec![Box::new(AlwaysPlayFirst), Box::new(AlwaysPlayFirst)];
+        let report = simulate(&config, &mut strategies, 20).unwrap();
+        assert_eq!(report.games_played, 20);
+        // a deterministic strategy against a fixed deal would always score the
+        // same; seeing more than one outcome confirms each game gets its own deal
+        assert!(report.histogram.len() > 1);
+    }
+}
@@ -0,0 +1,186 @@
+//! pluggable bot strategies and the observation they act on
+
+use std::collections::HashSet;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use {Action, Card, CardId, CardInfo, CardInfoKind, Field, Knowledge, PlayerInfo, MAX_HINT_TOKENS};
+
+/// everything a strategy may look at when choosing its next action.
+/// the acting player's own hand is visible only as `CardId`s; everything
+/// else (other hands, the field, discards, tokens, accumulated knowledge)
+/// is fully revealed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Observation {
+    pub player: usize,
+    pub own_hand: Vec<CardId>,
+    pub others: Vec<PlayerInfo>,
+    pub field: Field,
+    pub discards: Vec<Card>,
+    pub hint_tokens: usize,
+    pub fuse_tokens: usize,
+    pub knowledge: Knowledge,
+}
+
+/// a player's filtered view of the game, as sent to remote clients by the
+/// optional websocket server: same shape a `Strategy` observes
+pub type PlayerView = Observation;
+
+/// a pluggable bot: given an `Observation`, decide on the next `Action`
+pub trait Strategy {
+    fn decide(&mut self, obs: &Observation) -> Action;
+}
+
+/// picks uniformly at random among moves that are always legal to attempt
+/// under the current rules; useful as a baseline to benchmark smarter bots
+/// against
+pub struct RandomStrategy<R> {
+    rng: R,
+}
+
+impl<R: Rng> RandomStrategy<R> {
+    pub fn new(rng: R) -> Self {
+        RandomStrategy { rng }
+    }
+}
+
+impl<R: Rng> Strategy for RandomStrategy<R> {
+    fn decide(&mut self, obs: &Observation) -> Action {
+        let mut candidates: Vec<Action> = obs.own_hand.iter().map(|&id| Action::Play(id)).collect();
+        if obs.hint_tokens < MAX_HINT_TOKENS {
+            candidates.extend(obs.own_hand.iter().map(|&id| Action::Discard(id)));
+        }
+        if obs.hint_tokens > 0 {
+            for other in &obs.others {
+                let mut seen_colors = HashSet::new();
+                let mut seen_numbers = HashSet::new();
+                for card in &other.hands {
+                    if seen_colors.insert(card.color) {
+                        candidates.push(Action::Tell(CardInfo::new(
+                            other.player_id,
+                            CardInfoKind::Color(card.color),
+                        )));
+                    }
+                    if seen_numbers.insert(card.number) {
+                        candidates.push(Action::Tell(CardInfo::new(
+                            other.player_id,
+                            CardInfoKind::Number(card.number),
+                        )));
+                    }
+                }
+            }
+        }
+        let idx = self.rng.gen_range(0, candidates.len());
+        candidates[idx].clone()
+    }
+}
+
+/// scaffolding for a bot that reasons over `Observation::knowledge`: plays a
+/// card it knows must be safe, otherwise falls back to hinting or discarding
+pub struct InformationStrategy<R> {
+    rng: R,
+}
+
+impl<R: Rng> InformationStrategy<R> {
+    pub fn new(rng: R) -> Self {
+        InformationStrategy { rng }
+    }
+    /// whether every possibility left for this card would currently land on the field
+    fn known_playable(&self, obs: &Observation, id: CardId) -> bool {
+        let known = match obs.knowledge.get(&obs.player).and_then(|k| k.get(&id)) {
+            Some(known) => known,
+            None => return false,
+        };
+        known.colors.iter().all(|&color| {
+            known
+                .numbers
+                .iter()
+                .all(|&number| obs.field.would_land(color, number))
+        })
+    }
+}
+
+impl<R: Rng> Strategy for InformationStrategy<R> {
+    fn decide(&mut self, obs: &Observation) -> Action {
+        if let Some(&id) = obs
+            .own_hand
+            .iter()
+            .find(|&&id| self.known_playable(obs, id))
+        {
+            return Action::Play(id);
+        }
+        if obs.hint_tokens > 0 {
+            if let Some(other) = obs.others.iter().find(|p| !p.hands.is_empty()) {
+                let card = other.hands[0];
+                return Action::Tell(CardInfo::new(
+                    other.player_id,
+                    CardInfoKind::Color(card.color),
+                ));
+            }
+        }
+        let idx = self.rng.gen_range(0, obs.own_hand.len());
+        Action::Discard(obs.own_hand[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Color, Config, Number};
+
+    #[test]
+    fn random_strategy_never_discards_while_hint_tokens_are_full() {
+        let game = Config::new(2).unwrap().build();
+        let obs = game.observation(0).unwrap();
+        let mut strategy = RandomStrategy::new(rand::thread_rng());
+        for _ in 0..50 {
+            match strategy.decide(&obs) {
+                Action::Discard(_) => panic!("discarded while hint tokens were full"),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn random_strategy_never_tells_when_no_hint_tokens_remain() {
+        let game = Config::new(2).unwrap().build();
+        let mut obs = game.observation(0).unwrap();
+        obs.hint_tokens = 0;
+        let mut strategy = RandomStrategy::new(rand::thread_rng());
+        for _ in 0..50 {
+            match strategy.decide(&obs) {
+                Action::Tell(_) => panic!("told with no hint tokens"),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn information_strategy_plays_a_card_known_to_be_safe() {
+        let game = Config::new(2).unwrap().build();
+        let mut obs = game.observation(0).unwrap();
+        let id = obs.own_hand[0];
+        let known = obs.knowledge.get_mut(&0).unwrap().get_mut(&id).unwrap();
+        known.colors = [Color::Red].iter().cloned().collect();
+        known.numbers = [Number::One].iter().cloned().collect();
+
+        let mut strategy = InformationStrategy::new(rand::thread_rng());
+        match strategy.decide(&obs) {
+            Action::Play(played) => assert_eq!(played, id),
+            other => panic!("expected Play, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn information_strategy_tells_when_nothing_is_known_playable() {
+        let game = Config::new(2).unwrap().build();
+        let obs = game.observation(0).unwrap();
+        assert!(obs.hint_tokens > 0);
+        let mut strategy = InformationStrategy::new(rand::thread_rng());
+        match strategy.decide(&obs) {
+            Action::Tell(_) => {}
+            other => panic!("expected Tell, got {:?}", other),
+        }
+    }
+}